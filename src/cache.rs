@@ -0,0 +1,376 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use ansi_term::{Color, Style};
+use rkyv::{
+    check_archived_root, ser::serializers::AllocSerializer, ser::Serializer, AlignedVec, Archive,
+    Deserialize, Infallible, Serialize,
+};
+use tempfile::NamedTempFile;
+
+use crate::context::Context;
+use crate::segment::{FillSegment, Segment};
+
+/// Bumped whenever the archived layout below changes; a mismatch is
+/// rejected outright rather than partially (mis)interpreted.
+const SCHEMA_VERSION: u32 = 3;
+
+/// Implemented by modules whose rendered segments are pure functions of a
+/// known, finite set of env vars and files, and can be memoized to disk.
+pub trait Cacheable {
+    /// Name the cache file is keyed under; matches the module name.
+    fn cache_name(&self) -> &'static str;
+
+    /// Environment variables this module's output depends on.
+    fn cache_env_keys(&self) -> &[&'static str] {
+        &[]
+    }
+
+    /// Files this module reads; only path, size and mtime are fingerprinted.
+    fn cache_files(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Hash of any other input the rendered segments depend on (e.g. config).
+    fn cache_extra_hash(&self) -> u64 {
+        0
+    }
+}
+
+/// Computes a fingerprint for a cacheable module's declared inputs.
+pub fn fingerprint(context: &Context, module: &dyn Cacheable) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    module.cache_name().hash(&mut hasher);
+
+    for key in module.cache_env_keys() {
+        key.hash(&mut hasher);
+        context.get_env(key).hash(&mut hasher);
+    }
+
+    for file in module.cache_files() {
+        file.hash(&mut hasher);
+        match fs::metadata(&file) {
+            Ok(meta) => {
+                meta.len().hash(&mut hasher);
+                let mtime_nanos = meta
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                    .map(|d| d.as_nanos())
+                    .unwrap_or(0);
+                mtime_nanos.hash(&mut hasher);
+            }
+            // A missing file is itself a distinct, hashable input.
+            Err(_) => "missing".hash(&mut hasher),
+        }
+    }
+
+    module.cache_extra_hash().hash(&mut hasher);
+
+    hasher.finish()
+}
+
+#[derive(Archive, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[archive(check_bytes)]
+struct CacheHeader {
+    schema_version: u32,
+    fingerprint: u64,
+}
+
+#[derive(Archive, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[archive(check_bytes)]
+enum CachedColor {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Purple,
+    Cyan,
+    White,
+    Fixed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl From<Color> for CachedColor {
+    fn from(color: Color) -> Self {
+        match color {
+            Color::Black => CachedColor::Black,
+            Color::Red => CachedColor::Red,
+            Color::Green => CachedColor::Green,
+            Color::Yellow => CachedColor::Yellow,
+            Color::Blue => CachedColor::Blue,
+            Color::Purple => CachedColor::Purple,
+            Color::Cyan => CachedColor::Cyan,
+            Color::White => CachedColor::White,
+            Color::Fixed(n) => CachedColor::Fixed(n),
+            Color::RGB(r, g, b) => CachedColor::Rgb(r, g, b),
+        }
+    }
+}
+
+impl From<CachedColor> for Color {
+    fn from(color: CachedColor) -> Self {
+        match color {
+            CachedColor::Black => Color::Black,
+            CachedColor::Red => Color::Red,
+            CachedColor::Green => Color::Green,
+            CachedColor::Yellow => Color::Yellow,
+            CachedColor::Blue => Color::Blue,
+            CachedColor::Purple => Color::Purple,
+            CachedColor::Cyan => Color::Cyan,
+            CachedColor::White => Color::White,
+            CachedColor::Fixed(n) => Color::Fixed(n),
+            CachedColor::Rgb(r, g, b) => Color::RGB(r, g, b),
+        }
+    }
+}
+
+#[derive(Archive, Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[archive(check_bytes)]
+struct CachedStyle {
+    foreground: Option<CachedColor>,
+    background: Option<CachedColor>,
+    is_bold: bool,
+    is_dimmed: bool,
+    is_italic: bool,
+    is_underline: bool,
+    is_blink: bool,
+    is_reverse: bool,
+    is_hidden: bool,
+    is_strikethrough: bool,
+}
+
+impl From<Style> for CachedStyle {
+    fn from(style: Style) -> Self {
+        CachedStyle {
+            foreground: style.foreground.map(CachedColor::from),
+            background: style.background.map(CachedColor::from),
+            is_bold: style.is_bold,
+            is_dimmed: style.is_dimmed,
+            is_italic: style.is_italic,
+            is_underline: style.is_underline,
+            is_blink: style.is_blink,
+            is_reverse: style.is_reverse,
+            is_hidden: style.is_hidden,
+            is_strikethrough: style.is_strikethrough,
+        }
+    }
+}
+
+impl From<CachedStyle> for Style {
+    fn from(style: CachedStyle) -> Self {
+        Style {
+            foreground: style.foreground.map(Color::from),
+            background: style.background.map(Color::from),
+            is_bold: style.is_bold,
+            is_dimmed: style.is_dimmed,
+            is_italic: style.is_italic,
+            is_underline: style.is_underline,
+            is_blink: style.is_blink,
+            is_reverse: style.is_reverse,
+            is_hidden: style.is_hidden,
+            is_strikethrough: style.is_strikethrough,
+        }
+    }
+}
+
+#[derive(Archive, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[archive(check_bytes)]
+enum CachedSegment {
+    Plain(String),
+    Styled(Option<CachedStyle>, String),
+    Fill(Option<CachedStyle>, String),
+    LineTerm,
+}
+
+impl From<&Segment> for CachedSegment {
+    fn from(segment: &Segment) -> Self {
+        match segment {
+            Segment::Plain(value) => CachedSegment::Plain(value.clone()),
+            Segment::Styled(text) => {
+                CachedSegment::Styled(text.style.map(CachedStyle::from), text.value.clone())
+            }
+            Segment::Fill(fill) => {
+                CachedSegment::Fill(fill.style.map(CachedStyle::from), fill.value.clone())
+            }
+            Segment::LineTerm => CachedSegment::LineTerm,
+        }
+    }
+}
+
+impl From<CachedSegment> for Segment {
+    fn from(cached: CachedSegment) -> Self {
+        match cached {
+            CachedSegment::Plain(value) => Segment::Plain(value),
+            CachedSegment::Styled(style, value) => {
+                Segment::Styled(crate::segment::TextSegment {
+                    style: style.map(Style::from),
+                    value,
+                })
+            }
+            CachedSegment::Fill(style, value) => Segment::Fill(FillSegment {
+                style: style.map(Style::from),
+                value,
+            }),
+            CachedSegment::LineTerm => Segment::LineTerm,
+        }
+    }
+}
+
+#[derive(Archive, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[archive(check_bytes)]
+struct CachedModule {
+    header: CacheHeader,
+    segments: Vec<CachedSegment>,
+}
+
+/// An on-disk store of memoized module output, keyed by module name.
+pub struct ModuleCache {
+    dir: PathBuf,
+}
+
+impl ModuleCache {
+    pub fn new(dir: PathBuf) -> Self {
+        ModuleCache { dir }
+    }
+
+    /// Builds a `ModuleCache` for `context`. `STARSHIP_CACHE`, if set,
+    /// overrides the root directory; otherwise falls back to the platform
+    /// cache dir, if one can be determined.
+    pub fn from_env(context: &Context) -> Option<Self> {
+        if let Some(dir) = context.get_env("STARSHIP_CACHE") {
+            return Some(ModuleCache::new(PathBuf::from(dir)));
+        }
+
+        dirs_next::cache_dir().map(|dir| ModuleCache::new(dir.join("starship")))
+    }
+
+    fn path_for(&self, module_name: &str) -> PathBuf {
+        self.dir.join(format!("{}.cache", module_name))
+    }
+
+    /// Returns the cached segments for `module_name` if a valid entry
+    /// exists whose fingerprint matches `fingerprint`; any other failure is
+    /// treated as a plain cache miss.
+    pub fn load(&self, module_name: &str, fingerprint: u64) -> Option<Vec<Segment>> {
+        let bytes = fs::read(self.path_for(module_name)).ok()?;
+        let archived = check_archived_root::<CachedModule>(&bytes).ok()?;
+
+        if archived.header.schema_version != SCHEMA_VERSION || archived.header.fingerprint != fingerprint {
+            return None;
+        }
+
+        let cached: CachedModule = archived.deserialize(&mut Infallible).ok()?;
+        Some(cached.segments.into_iter().map(Segment::from).collect())
+    }
+
+    /// Persists `segments` for `module_name` under `fingerprint`. Writes
+    /// atomically (temp file, then rename) so a half-written cache is
+    /// never visible.
+    pub fn store(&self, module_name: &str, fingerprint: u64, segments: &[Segment]) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        let cached = CachedModule {
+            header: CacheHeader {
+                schema_version: SCHEMA_VERSION,
+                fingerprint,
+            },
+            segments: segments.iter().map(CachedSegment::from).collect(),
+        };
+
+        let bytes: AlignedVec = {
+            let mut serializer = AllocSerializer::<256>::default();
+            serializer
+                .serialize_value(&cached)
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+            serializer.into_serializer().into_inner()
+        };
+
+        let mut tmp_file = NamedTempFile::new_in(&self.dir)?;
+        tmp_file.write_all(&bytes)?;
+        tmp_file
+            .persist(self.path_for(module_name))
+            .map_err(|err| err.error)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments() -> Vec<Segment> {
+        vec![
+            Segment::Plain("v".to_string()),
+            Segment::Styled(crate::segment::TextSegment {
+                style: Some(Style::new().fg(Color::Red).bold()),
+                value: "main".to_string(),
+            }),
+            Segment::Fill(FillSegment {
+                style: None,
+                value: "-".to_string(),
+            }),
+            Segment::LineTerm,
+        ]
+    }
+
+    #[test]
+    fn store_then_load_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ModuleCache::new(dir.path().to_path_buf());
+        let expected = segments();
+
+        cache.store("git_branch", 42, &expected).unwrap();
+        let loaded = cache.load("git_branch", 42).expect("cache should hit");
+
+        assert_eq!(loaded, expected);
+    }
+
+    #[test]
+    fn load_rejects_corrupt_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ModuleCache::new(dir.path().to_path_buf());
+
+        fs::write(cache.path_for("git_branch"), b"not a valid cache file").unwrap();
+
+        assert_eq!(cache.load("git_branch", 42), None);
+    }
+
+    #[test]
+    fn load_rejects_mismatched_schema_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ModuleCache::new(dir.path().to_path_buf());
+
+        // Write a cache file by hand with a schema version other than the
+        // current one.
+        let stale = CachedModule {
+            header: CacheHeader {
+                schema_version: SCHEMA_VERSION + 1,
+                fingerprint: 42,
+            },
+            segments: segments().iter().map(CachedSegment::from).collect(),
+        };
+        let mut serializer = AllocSerializer::<256>::default();
+        serializer.serialize_value(&stale).unwrap();
+        let bytes = serializer.into_serializer().into_inner();
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(cache.path_for("git_branch"), &bytes).unwrap();
+
+        assert_eq!(cache.load("git_branch", 42), None);
+    }
+
+    #[test]
+    fn load_rejects_mismatched_fingerprint() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ModuleCache::new(dir.path().to_path_buf());
+
+        cache.store("git_branch", 42, &segments()).unwrap();
+
+        assert_eq!(cache.load("git_branch", 43), None);
+    }
+}
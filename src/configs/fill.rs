@@ -0,0 +1,23 @@
+use crate::config::{ModuleConfig, RootModuleConfig};
+
+// No `weight` field here: `[fill]` is a single shared table, so every
+// `$fill` occurrence in a format string reads the same config and a
+// per-occurrence weighted mode has no way to differ between them. Only
+// the even, remainder-exact split (see `distribute_fill_widths` in
+// `module.rs`) is supported.
+#[derive(Clone, ModuleConfig)]
+pub struct FillConfig<'a> {
+    pub symbol: &'a str,
+    pub style: &'a str,
+    pub disabled: bool,
+}
+
+impl<'a> RootModuleConfig<'a> for FillConfig<'a> {
+    fn new() -> Self {
+        FillConfig {
+            symbol: ".",
+            style: "bold black",
+            disabled: false,
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use crate::config::{ModuleConfig, RootModuleConfig};
+
+use indexmap::IndexMap;
+
+#[derive(Clone, ModuleConfig)]
+pub struct KubernetesConfig<'a> {
+    pub symbol: &'a str,
+    pub format: &'a str,
+    pub style: &'a str,
+    pub disabled: bool,
+    pub context_aliases: IndexMap<String, &'a str>,
+    pub user_aliases: IndexMap<String, &'a str>,
+    pub cluster_aliases: IndexMap<String, &'a str>,
+}
+
+impl<'a> RootModuleConfig<'a> for KubernetesConfig<'a> {
+    fn new() -> Self {
+        KubernetesConfig {
+            symbol: "☸ ",
+            format: "[$symbol$context( \\($namespace\\))]($style) in ",
+            style: "cyan bold",
+            disabled: true,
+            context_aliases: IndexMap::new(),
+            user_aliases: IndexMap::new(),
+            cluster_aliases: IndexMap::new(),
+        }
+    }
+}
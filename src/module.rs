@@ -204,13 +204,13 @@ where
     if chunks.is_empty() {
         current
     } else {
-        let fill_size = term_width
-            .map(|tw| if tw > used { Some(tw - used) } else { None })
-            .flatten()
-            .map(|remaining| remaining / chunks.len());
+        let remaining = term_width.and_then(|tw| tw.checked_sub(used));
+        let fill_sizes = distribute_fill_widths(remaining, chunks.len());
+
         chunks
             .into_iter()
-            .flat_map(|(strs, fill)| {
+            .zip(fill_sizes)
+            .flat_map(|((strs, fill), fill_size)| {
                 strs.into_iter()
                     .chain(std::iter::once(fill.ansi_string(fill_size)))
             })
@@ -219,6 +219,34 @@ where
     }
 }
 
+/// Splits `remaining` columns evenly across `count` fills, handing the
+/// integer remainder to the leftmost fills one column at a time.
+fn distribute_fill_widths(remaining: Option<usize>, count: usize) -> Vec<Option<usize>> {
+    let remaining = match remaining {
+        Some(remaining) => remaining,
+        None => return vec![None; count],
+    };
+
+    if count == 0 {
+        return Vec::new();
+    }
+
+    let base = remaining / count;
+    let mut leftover = remaining - base * count;
+
+    (0..count)
+        .map(|_| {
+            let size = if leftover > 0 {
+                leftover -= 1;
+                base + 1
+            } else {
+                base
+            };
+            Some(size)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -289,4 +317,54 @@ mod tests {
 
         assert!(!module.is_empty());
     }
+
+    #[test]
+    fn test_distribute_fill_widths_unknown_term_width() {
+        assert_eq!(distribute_fill_widths(None, 3), vec![None, None, None]);
+    }
+
+    #[test]
+    fn test_distribute_fill_widths_even() {
+        // 10 columns across 3 fills: the 1-column remainder goes to the
+        // leftmost fill instead of being discarded.
+        assert_eq!(
+            distribute_fill_widths(Some(10), 3),
+            vec![Some(4), Some(3), Some(3)]
+        );
+    }
+
+    #[test]
+    fn test_distribute_fill_widths_no_fills() {
+        assert_eq!(distribute_fill_widths(Some(10), 0), Vec::<Option<usize>>::new());
+    }
+
+    #[test]
+    fn test_ansi_strings_for_shell_splits_multiple_fills_exactly() {
+        let module = Module {
+            config: None,
+            name: "unit_test".to_string(),
+            description: "test".to_string(),
+            segments: vec![
+                Segment::Fill(FillSegment {
+                    style: None,
+                    value: ".".to_string(),
+                }),
+                Segment::Fill(FillSegment {
+                    style: None,
+                    value: ".".to_string(),
+                }),
+                Segment::Fill(FillSegment {
+                    style: None,
+                    value: ".".to_string(),
+                }),
+            ],
+            duration: Duration::default(),
+        };
+        let rendered: Vec<String> = module
+            .ansi_strings_for_shell(Shell::Unknown, Some(10))
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(rendered, vec![".".repeat(4), ".".repeat(3), ".".repeat(3)]);
+    }
 }
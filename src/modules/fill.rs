@@ -0,0 +1,21 @@
+use super::{Context, Module, RootModuleConfig};
+
+use crate::configs::fill::FillConfig;
+use crate::segment::{FillSegment, Segment};
+
+/// Creates a module that expands to fill the remaining terminal width.
+pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
+    let mut module = context.new_module("fill");
+    let config: FillConfig = FillConfig::try_load(module.config);
+
+    if config.disabled {
+        return None;
+    };
+
+    module.set_segments(vec![Segment::Fill(FillSegment {
+        style: crate::config::parse_style_string(config.style),
+        value: config.symbol.to_string(),
+    })]);
+
+    Some(module)
+}
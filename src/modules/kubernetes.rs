@@ -1,15 +1,68 @@
 use yaml_rust::YamlLoader;
 
+use indexmap::IndexMap;
 use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
 use std::env;
+use std::hash::{Hash, Hasher};
 use std::path;
 
 use super::{Context, Module, RootModuleConfig};
 
+use crate::cache::{Cacheable, ModuleCache};
 use crate::configs::kubernetes::KubernetesConfig;
 use crate::formatter::StringFormatter;
 use crate::utils;
 
+/// The inputs `kubernetes::module` reads: the `KUBECONFIG` env var, every
+/// kubeconfig file on that path, and the config fields baked into the
+/// rendered segments.
+struct KubernetesCacheInputs {
+    kubeconfig_paths: Vec<path::PathBuf>,
+    config_hash: u64,
+}
+
+impl Cacheable for KubernetesCacheInputs {
+    fn cache_name(&self) -> &'static str {
+        "kubernetes"
+    }
+
+    fn cache_env_keys(&self) -> &[&'static str] {
+        &["KUBECONFIG"]
+    }
+
+    fn cache_files(&self) -> Vec<path::PathBuf> {
+        self.kubeconfig_paths.clone()
+    }
+
+    fn cache_extra_hash(&self) -> u64 {
+        self.config_hash
+    }
+}
+
+/// Hashes the subset of `KubernetesConfig` that feeds into the rendered
+/// segments, for folding into the module's cache fingerprint.
+fn hash_kubernetes_config(config: &KubernetesConfig) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    config.symbol.hash(&mut hasher);
+    config.format.hash(&mut hasher);
+    config.style.hash(&mut hasher);
+
+    for aliases in [
+        &config.context_aliases,
+        &config.user_aliases,
+        &config.cluster_aliases,
+    ] {
+        for (pattern, replacement) in aliases {
+            pattern.hash(&mut hasher);
+            replacement.hash(&mut hasher);
+        }
+    }
+
+    hasher.finish()
+}
+
 fn get_kube_context(filename: path::PathBuf) -> Option<String> {
     let contents = utils::read_file(filename).ok()?;
 
@@ -27,7 +80,21 @@ fn get_kube_context(filename: path::PathBuf) -> Option<String> {
     Some(current_ctx.to_string())
 }
 
-fn get_kube_ns(filename: path::PathBuf, current_ctx: String) -> Option<String> {
+/// The `namespace`, `user` and `cluster` of a matched context entry, each
+/// absent if the entry doesn't set that field (or sets it to an empty
+/// string).
+#[derive(Default)]
+struct KubeContextValues {
+    namespace: Option<String>,
+    user: Option<String>,
+    cluster: Option<String>,
+}
+
+/// Finds the context entry named `current_ctx` in `filename` and extracts
+/// its `namespace`/`user`/`cluster` in a single parse. Returns `None` if
+/// `filename` has no context named `current_ctx`; callers merging a
+/// multi-file `KUBECONFIG` should fold results across every matching file.
+fn get_kube_ctx_values(filename: path::PathBuf, current_ctx: &str) -> Option<KubeContextValues> {
     let contents = utils::read_file(filename).ok()?;
 
     let yaml_docs = YamlLoader::load_from_str(&contents).ok()?;
@@ -36,36 +103,43 @@ fn get_kube_ns(filename: path::PathBuf, current_ctx: String) -> Option<String> {
     }
     let conf = &yaml_docs[0];
 
-    let ns = conf["contexts"].as_vec().and_then(|contexts| {
-        contexts
-            .iter()
-            .filter_map(|ctx| Some((ctx, ctx["name"].as_str()?)))
-            .find(|(_, name)| *name == current_ctx)
-            .and_then(|(ctx, _)| ctx["context"]["namespace"].as_str())
-    })?;
+    let ctx = conf["contexts"]
+        .as_vec()?
+        .iter()
+        .find(|ctx| ctx["name"].as_str() == Some(current_ctx))?;
 
-    if ns.is_empty() {
-        return None;
-    }
-    Some(ns.to_owned())
+    let field = |name: &str| {
+        ctx["context"][name]
+            .as_str()
+            .filter(|value| !value.is_empty())
+            .map(str::to_owned)
+    };
+
+    Some(KubeContextValues {
+        namespace: field("namespace"),
+        user: field("user"),
+        cluster: field("cluster"),
+    })
 }
 
-fn get_kube_context_name<'a>(config: &'a KubernetesConfig, kube_ctx: &'a str) -> Cow<'a, str> {
-    if let Some(val) = config.context_aliases.get(kube_ctx) {
+/// Rewrites `value` using `aliases` (literal match takes precedence over
+/// regex, tried in insertion order); returns `value` unchanged if nothing
+/// matches. Shared by the `context`, `user` and `cluster` variables.
+fn get_alias<'a>(aliases: &'a IndexMap<String, &'a str>, value: &'a str) -> Cow<'a, str> {
+    if let Some(val) = aliases.get(value) {
         return Cow::Borrowed(val);
     }
 
-    config
-        .context_aliases
+    aliases
         .iter()
         .find_map(|(k, v)| {
             let re = regex::Regex::new(&format!("^{}$", k)).ok()?;
-            match re.replace(kube_ctx, *v) {
+            match re.replace(value, *v) {
                 Cow::Owned(replaced) => Some(Cow::Owned(replaced)),
                 _ => None,
             }
         })
-        .unwrap_or(Cow::Borrowed(kube_ctx))
+        .unwrap_or(Cow::Borrowed(value))
 }
 
 pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
@@ -84,10 +158,47 @@ pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
         .get_env("KUBECONFIG")
         .unwrap_or(default_config_file.to_str()?.to_string());
 
-    let kube_ctx = env::split_paths(&kube_cfg).find_map(get_kube_context)?;
+    let cache_inputs = KubernetesCacheInputs {
+        kubeconfig_paths: env::split_paths(&kube_cfg).collect(),
+        config_hash: hash_kubernetes_config(&config),
+    };
+    let cache = ModuleCache::from_env(context);
+    let cache_fingerprint = cache
+        .as_ref()
+        .map(|_| crate::cache::fingerprint(context, &cache_inputs));
+
+    if let (Some(cache), Some(fingerprint)) = (&cache, cache_fingerprint) {
+        if let Some(segments) = cache.load(cache_inputs.cache_name(), fingerprint) {
+            module.set_segments(segments);
+            return Some(module);
+        }
+    }
 
-    let kube_ns =
-        env::split_paths(&kube_cfg).find_map(|filename| get_kube_ns(filename, kube_ctx.clone()));
+    let kube_ctx = cache_inputs
+        .kubeconfig_paths
+        .iter()
+        .cloned()
+        .find_map(get_kube_context)?;
+
+    // Fold per-field across every matching file rather than taking the
+    // first match wholesale, since a merged KUBECONFIG can split one
+    // context's fields across files.
+    let KubeContextValues {
+        namespace: kube_ns,
+        user: kube_user,
+        cluster: kube_cluster,
+    } = cache_inputs
+        .kubeconfig_paths
+        .iter()
+        .cloned()
+        .filter_map(|filename| get_kube_ctx_values(filename, &kube_ctx))
+        .fold(KubeContextValues::default(), |acc, values| {
+            KubeContextValues {
+                namespace: acc.namespace.or(values.namespace),
+                user: acc.user.or(values.user),
+                cluster: acc.cluster.or(values.cluster),
+            }
+        });
 
     let parsed = StringFormatter::new(config.format).and_then(|formatter| {
         formatter
@@ -100,20 +211,34 @@ pub fn module<'a>(context: &'a Context) -> Option<Module<'a>> {
                 _ => None,
             })
             .map(|variable| match variable {
-                "context" => Some(Ok(get_kube_context_name(&config, &kube_ctx))),
+                "context" => Some(Ok(get_alias(&config.context_aliases, &kube_ctx))),
                 "namespace" => kube_ns.as_ref().map(|s| Ok(Cow::Borrowed(s.as_str()))),
+                "user" => kube_user
+                    .as_ref()
+                    .map(|s| Ok(get_alias(&config.user_aliases, s))),
+                "cluster" => kube_cluster
+                    .as_ref()
+                    .map(|s| Ok(get_alias(&config.cluster_aliases, s))),
                 _ => None,
             })
             .parse(None)
     });
 
-    module.set_segments(match parsed {
+    let segments = match parsed {
         Ok(segments) => segments,
         Err(error) => {
             log::warn!("Error in module `kubernetes`: \n{}", error);
             return None;
         }
-    });
+    };
+
+    if let (Some(cache), Some(fingerprint)) = (&cache, cache_fingerprint) {
+        if let Err(error) = cache.store(cache_inputs.cache_name(), fingerprint, &segments) {
+            log::debug!("Failed to write kubernetes module cache: \n{}", error);
+        }
+    }
+
+    module.set_segments(segments);
 
     Some(module)
 }
@@ -152,6 +277,7 @@ users: []
 
         let actual = ModuleRenderer::new("kubernetes")
             .path(dir.path())
+            .env("STARSHIP_CACHE", dir.path().to_string_lossy().as_ref())
             .env("KUBECONFIG", filename.to_string_lossy().as_ref())
             .collect();
 
@@ -185,6 +311,7 @@ users: []
 
         let actual = ModuleRenderer::new("kubernetes")
             .path(dir.path())
+            .env("STARSHIP_CACHE", dir.path().to_string_lossy().as_ref())
             .env("KUBECONFIG", filename.to_string_lossy().as_ref())
             .config(config)
             .collect();
@@ -278,6 +405,7 @@ users: []
 
         let actual = ModuleRenderer::new("kubernetes")
             .path(dir.path())
+            .env("STARSHIP_CACHE", dir.path().to_string_lossy().as_ref())
             .env("KUBECONFIG", filename.to_string_lossy().as_ref())
             .config(toml::toml! {
                 [kubernetes]
@@ -321,6 +449,7 @@ users: []
 
         let actual = ModuleRenderer::new("kubernetes")
             .path(dir.path())
+            .env("STARSHIP_CACHE", dir.path().to_string_lossy().as_ref())
             .env("KUBECONFIG", filename.to_string_lossy().as_ref())
             .config(toml::toml! {
                 [kubernetes]
@@ -369,6 +498,7 @@ users: []
 
         let actual = ModuleRenderer::new("kubernetes")
             .path(dir.path())
+            .env("STARSHIP_CACHE", dir.path().to_string_lossy().as_ref())
             .env("KUBECONFIG", filename.to_string_lossy().as_ref())
             .config(toml::toml! {
                 [kubernetes]
@@ -427,6 +557,7 @@ users: []
         // Test current_context first
         let actual_cc_first = ModuleRenderer::new("kubernetes")
             .path(dir.path())
+            .env("STARSHIP_CACHE", dir.path().to_string_lossy().as_ref())
             .env(
                 "KUBECONFIG",
                 env::join_paths([&filename_cc, &filename_ctx])
@@ -442,6 +573,7 @@ users: []
         // And tes with context and namespace first
         let actual_ctx_first = ModuleRenderer::new("kubernetes")
             .path(dir.path())
+            .env("STARSHIP_CACHE", dir.path().to_string_lossy().as_ref())
             .env(
                 "KUBECONFIG",
                 env::join_paths([&filename_ctx, &filename_cc])
@@ -463,4 +595,173 @@ users: []
 
         dir.close()
     }
+
+    #[test]
+    fn test_multiple_config_files_merge_fields_across_files() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        // Both files define a `test_context` entry, but each only
+        // populates some of its fields -- as can happen when `KUBECONFIG`
+        // merges configs from different sources (e.g. one cluster-admin
+        // tool writes `namespace`, another writes `user`/`cluster`).
+        let filename_ns = dir.path().join("config_ns");
+        let mut file_ns = File::create(&filename_ns)?;
+        file_ns.write_all(
+            b"
+apiVersion: v1
+clusters: []
+contexts:
+  - context:
+      namespace: test_namespace
+    name: test_context
+current-context: test_context
+kind: Config
+preferences: {}
+users: []
+",
+        )?;
+        file_ns.sync_all()?;
+
+        let filename_uc = dir.path().join("config_uc");
+        let mut file_uc = File::create(&filename_uc)?;
+        file_uc.write_all(
+            b"
+apiVersion: v1
+clusters: []
+contexts:
+  - context:
+      cluster: test_cluster
+      user: test_user
+    name: test_context
+kind: Config
+preferences: {}
+users: []
+",
+        )?;
+        file_uc.sync_all()?;
+
+        let actual = ModuleRenderer::new("kubernetes")
+            .path(dir.path())
+            .env("STARSHIP_CACHE", dir.path().to_string_lossy().as_ref())
+            .env(
+                "KUBECONFIG",
+                env::join_paths([&filename_ns, &filename_uc])
+                    .unwrap()
+                    .to_string_lossy(),
+            )
+            .config(toml::toml! {
+                [kubernetes]
+                disabled = false
+                format = "[$symbol$context( \\($namespace\\))( as $user)( on $cluster)]($style) in "
+            })
+            .collect();
+
+        let expected = Some(format!(
+            "{} in ",
+            Color::Cyan.bold().paint(
+                "☸ test_context (test_namespace) as test_user on test_cluster"
+            )
+        ));
+        assert_eq!(expected, actual);
+
+        dir.close()
+    }
+
+    #[test]
+    fn test_user_and_cluster_variables() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        let filename = dir.path().join("config");
+
+        let mut file = File::create(&filename)?;
+        file.write_all(
+            b"
+apiVersion: v1
+clusters: []
+contexts:
+  - context:
+      cluster: test_cluster
+      user: test_user
+      namespace: test_namespace
+    name: test_context
+current-context: test_context
+kind: Config
+preferences: {}
+users: []
+",
+        )?;
+        file.sync_all()?;
+
+        let actual = ModuleRenderer::new("kubernetes")
+            .path(dir.path())
+            .env("STARSHIP_CACHE", dir.path().to_string_lossy().as_ref())
+            .env("KUBECONFIG", filename.to_string_lossy().as_ref())
+            .config(toml::toml! {
+                [kubernetes]
+                disabled = false
+                format = "[$symbol$context( \\($namespace\\))( as $user)( on $cluster)]($style) in "
+            })
+            .collect();
+
+        let expected = Some(format!(
+            "{} in ",
+            Color::Cyan.bold().paint(
+                "☸ test_context (test_namespace) as test_user on test_cluster"
+            )
+        ));
+        assert_eq!(expected, actual);
+
+        dir.close()
+    }
+
+    #[test]
+    fn test_user_and_cluster_aliases() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        let filename = dir.path().join("config");
+
+        let mut file = File::create(&filename)?;
+        file.write_all(
+            b"
+apiVersion: v1
+clusters: []
+contexts:
+  - context:
+      cluster: gke_infra-cluster-28cccff6_europe-west4_cluster-1
+      user: test-user@infra-cluster-28cccff6.iam.gserviceaccount.com
+      namespace: test_namespace
+    name: test_context
+current-context: test_context
+kind: Config
+preferences: {}
+users: []
+",
+        )?;
+        file.sync_all()?;
+
+        let actual = ModuleRenderer::new("kubernetes")
+            .path(dir.path())
+            .env("STARSHIP_CACHE", dir.path().to_string_lossy().as_ref())
+            .env("KUBECONFIG", filename.to_string_lossy().as_ref())
+            .config(toml::toml! {
+                [kubernetes]
+                disabled = false
+                format = "[$symbol$context( as $user)( on $cluster)]($style) in "
+                [kubernetes.user_aliases]
+                "(?P<user>.*)@.*\\.iam\\.gserviceaccount\\.com" = "$user"
+                [kubernetes.cluster_aliases]
+                "gke_.*_(?P<cluster>[\\w-]+)" = "$cluster"
+            })
+            .collect();
+
+        let expected = Some(format!(
+            "{} in ",
+            Color::Cyan
+                .bold()
+                .paint("☸ test_context as test-user on cluster-1")
+        ));
+        assert_eq!(expected, actual);
+
+        dir.close()
+    }
 }
@@ -0,0 +1,135 @@
+use ansi_term::{ANSIString, Style};
+use unicode_width::UnicodeWidthStr;
+
+/// A single piece of a module's rendered output.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Segment {
+    /// An unstyled run of text.
+    Plain(String),
+    /// A styled run of text.
+    Styled(TextSegment),
+    /// A segment that expands to fill the remaining terminal width, such as
+    /// the separator in a left/right-aligned prompt.
+    Fill(FillSegment),
+    /// Forces the segments around it onto separate lines.
+    LineTerm,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextSegment {
+    pub style: Option<Style>,
+    pub value: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FillSegment {
+    pub style: Option<Style>,
+    /// The (possibly multi-character) symbol repeated to fill the
+    /// available width, e.g. `"."` or `"─"`.
+    pub value: String,
+}
+
+impl FillSegment {
+    /// Renders this fill repeated to `width` columns. `None` means the
+    /// terminal width is unknown, in which case a single copy of the fill
+    /// symbol is emitted.
+    pub fn ansi_string(&self, width: Option<usize>) -> ANSIString<'static> {
+        let text = match width {
+            Some(width) => {
+                let symbol_width = self.value.width().max(1);
+                let repeat_count = width / symbol_width;
+                let text = self.value.repeat(repeat_count);
+                // pad leftover columns with spaces instead of relying on
+                // `format!`'s width padding, which counts characters rather
+                // than display columns and so mis-pads wide symbols
+                let padding = " ".repeat(width.saturating_sub(text.width()));
+                format!("{}{}", text, padding)
+            }
+            None => self.value.clone(),
+        };
+
+        match self.style {
+            Some(style) => style.paint(text),
+            None => ANSIString::from(text),
+        }
+    }
+}
+
+impl Segment {
+    pub fn value(&self) -> &str {
+        match self {
+            Segment::Plain(value) => value,
+            Segment::Styled(text) => &text.value,
+            Segment::Fill(fill) => &fill.value,
+            Segment::LineTerm => "\n",
+        }
+    }
+
+    pub fn width_graphemes(&self) -> usize {
+        self.value().width()
+    }
+
+    pub fn ansi_string(&self) -> ANSIString<'static> {
+        match self {
+            Segment::Plain(value) => ANSIString::from(value.clone()),
+            Segment::Styled(text) => match text.style {
+                Some(style) => style.paint(text.value.clone()),
+                None => ANSIString::from(text.value.clone()),
+            },
+            Segment::Fill(fill) => fill.ansi_string(None),
+            Segment::LineTerm => ANSIString::from("\n".to_string()),
+        }
+    }
+
+    /// Builds the (typically one-element) segment vector for a plain run of
+    /// styled text, splitting on `\n` into separate `LineTerm`-terminated
+    /// segments so multi-line module output lays out correctly.
+    pub fn from_text(style: Option<Style>, text: &str) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        let mut parts = text.split('\n').peekable();
+
+        while let Some(part) = parts.next() {
+            if !part.is_empty() {
+                segments.push(Segment::Styled(TextSegment {
+                    style,
+                    value: part.to_string(),
+                }));
+            }
+            if parts.peek().is_some() {
+                segments.push(Segment::LineTerm);
+            }
+        }
+
+        segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_pads_multi_width_symbol_to_exact_width() {
+        let fill = FillSegment {
+            style: None,
+            value: "ab".to_string(),
+        };
+
+        // "ab" is 2 columns wide; over a width of 5 it repeats twice
+        // (4 columns) and must be padded with exactly one space, not
+        // left 1 column short.
+        let rendered = fill.ansi_string(Some(5));
+        assert_eq!(rendered.to_string(), "abab ");
+        assert_eq!(rendered.width(), 5);
+    }
+
+    #[test]
+    fn fill_emits_one_copy_of_symbol_when_width_unknown() {
+        let fill = FillSegment {
+            style: None,
+            value: "ab".to_string(),
+        };
+
+        assert_eq!(fill.ansi_string(None).to_string(), "ab");
+    }
+}